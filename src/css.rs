@@ -33,7 +33,26 @@ pub enum Selector {
 pub struct SimpleSelector {
     pub tag_name: Option<String>,
     pub id: Option<String>,
-    pub class: Vec<String>
+    pub class: Vec<String>,
+    pub attributes: Vec<AttributeSelector>
+}
+
+// An attribute selector, e.g. `[disabled]` or `[type="text"]`.
+// 属性选择器，例如 `[disabled]` 或 `[type="text"]`
+#[derive(Debug)]
+pub struct AttributeSelector {
+    pub name: String,
+    pub operator: Option<AttrOperator>,
+    pub value: Option<String>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttrOperator {
+    Equals,    // [name=value] 完全相等
+    Includes,  // [name~=value] 空格分隔的列表中包含 value
+    Prefix,    // [name^=value] 以 value 开头
+    Suffix,    // [name$=value] 以 value 结尾
+    Substring  // [name*=value] 包含 value
 }
 
 /*
@@ -60,7 +79,9 @@ impl Selector {
     pub fn specificity(&self) -> Specificity {
         let Selector::Simple(ref simple) = *self;
         let a = simple.id.iter().count();
-        let b = simple.class.len();
+        // Attribute selectors count like classes.
+        // 属性选择器与类选择器的优先级相同
+        let b = simple.class.len() + simple.attributes.len();
         let c = simple.tag_name.iter().count();
 
         (a, b, c)
@@ -79,7 +100,8 @@ impl Selector {
 #[derive(Debug)]
 pub struct Declaration {
     pub name: String,
-    pub value: Value
+    pub value: Value,
+    pub important: bool // 是否带有 `!important`
 }
 
 // supports only a handful of CSS's many value types.
@@ -88,13 +110,23 @@ pub struct Declaration {
 pub enum Value {
     Keyword(String),
     Length(f32, Unit),
+    Number(f32), // 没有单位的数字，例如 `opacity: 0.5`
     ColorValue(Color),
+    // `linear-gradient(<angle>, <color>, <color>, ...)`, angle in degrees clockwise from "to top"
+    // 线性渐变：角度（从 "to top" 起顺时针的度数）与颜色列表
+    LinearGradient(f32, Vec<Color>),
     // insert more values here
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Unit {
     Px,
+    // Percentages of the viewport width / height / smaller side / larger side.
+    // 视口宽度 / 高度 / 较小边 / 较大边的百分比
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
     // insert more units here
 }
 
@@ -136,6 +168,14 @@ pub fn parse(source: String) -> Stylesheet {
     Stylesheet { rules: parser.parse_rules() }
 }
 
+/// Parse the declarations of an inline `style` attribute, e.g. `color: #ff0000; margin: 0px`.
+/// Declarations that are malformed or use unsupported syntax are dropped.
+/// 解析行内 `style` 属性中的声明，无法解析的声明会被丢弃
+pub fn parse_inline_style(source: String) -> Vec<Declaration> {
+    let mut parser = Parser { pos: 0, input: source };
+    parser.parse_declaration_list()
+}
+
 struct Parser {
     pos: usize,
     input: String
@@ -204,7 +244,7 @@ impl Parser {
     // 请注意缺少错误检查。一些格式错误的输入，如 ### 或 *foo* 将成功解析并产生奇怪的结果。
     // 真正的 CSS 解析器会丢弃这些无效的选择器。
     fn parse_simple_selector(&mut self) -> SimpleSelector {
-        let mut selector = SimpleSelector { tag_name: None, id: None, class: Vec::new() };
+        let mut selector = SimpleSelector { tag_name: None, id: None, class: Vec::new(), attributes: Vec::new() };
         while !self.eof() {
             match self.next_char() {
                 '#' => {
@@ -218,8 +258,13 @@ impl Parser {
                 '*' => {
                     self.consume_char();
                 }
+                '[' => {
+                    selector.attributes.push(self.parse_attribute_selector());
+                }
                 c if valid_identifier_char(c) => {
-                    selector.tag_name = Some(self.parse_identifier());
+                    // HTML tag names are case-insensitive, and the DOM stores them in lowercase.
+                    // HTML 标签名不区分大小写，DOM 中统一保存为小写
+                    selector.tag_name = Some(self.parse_identifier().to_ascii_lowercase());
                 }
                 _ => break
             }
@@ -228,6 +273,49 @@ impl Parser {
         selector
     }
 
+    /// Parse an attribute selector: `[name]`, or `[name<op>value]` where `<op>` is one of
+    /// `=`, `~=`, `^=`, `$=`, `*=` and the value may be quoted.
+    /// 解析属性选择器，属性值可以带引号
+    fn parse_attribute_selector(&mut self) -> AttributeSelector {
+        assert_eq!(self.consume_char(), '[');
+        self.consume_whitespace();
+        // HTML attribute names are case-insensitive, and the DOM stores them in lowercase.
+        // HTML 属性名不区分大小写，DOM 中统一保存为小写
+        let name = self.parse_identifier().to_ascii_lowercase();
+        self.consume_whitespace();
+
+        let operator = match self.consume_char() {
+            ']' => return AttributeSelector { name, operator: None, value: None },
+            '=' => AttrOperator::Equals,
+            c => {
+                let operator = match c {
+                    '~' => AttrOperator::Includes,
+                    '^' => AttrOperator::Prefix,
+                    '$' => AttrOperator::Suffix,
+                    '*' => AttrOperator::Substring,
+                    _ => panic!("Unexpected character {} in attribute selector", c)
+                };
+                assert_eq!(self.consume_char(), '=');
+                operator
+            }
+        };
+
+        self.consume_whitespace();
+        let value = match self.next_char() {
+            '"' | '\'' => {
+                let open_quote = self.consume_char();
+                let value = self.consume_while(|c| c != open_quote);
+                assert_eq!(self.consume_char(), open_quote);
+                value
+            }
+            _ => self.parse_identifier()
+        };
+        self.consume_whitespace();
+        assert_eq!(self.consume_char(), ']');
+
+        AttributeSelector { name, operator: Some(operator), value: Some(value) }
+    }
+
     /// Parse a list of declarations enclosed in `{ ... }`.
     /// 解析包含在 `{ ... }` 中的声明列表
     fn parse_declarations(&mut self) -> Vec<Declaration> {
@@ -247,66 +335,221 @@ impl Parser {
         declarations
     }
 
-    /// Parse one `<property>: <value>;` declaration.
+    /// Parse a list of declarations that runs to the end of the input, as in a `style` attribute.
+    /// A declaration that can't be parsed is skipped up to the next semicolon.
+    /// 解析一直到输入结尾的声明列表，例如 `style` 属性。无法解析的声明会被跳过，直到下一个分号
+    fn parse_declaration_list(&mut self) -> Vec<Declaration> {
+        let mut declarations = Vec::new();
+        loop {
+            self.consume_whitespace();
+            if self.eof() {
+                break;
+            }
+
+            if let Some(declaration) = self.try_parse_declaration() {
+                if self.eof() || self.next_char() == ';' {
+                    declarations.push(declaration);
+                }
+            }
+
+            // Skip the rest of this declaration, and its semicolon.
+            // 跳过这条声明剩下的部分以及分号
+            self.consume_while(|c| c != ';');
+            if !self.eof() {
+                self.consume_char();
+            }
+        }
+
+        declarations
+    }
+
+    /// Parse one `<property>: <value> [!important];` declaration.
+    /// The final semicolon may be omitted at the end of the input.
     /// /// 解析一个 `<property>: <value>;` 声明
     fn parse_declaration(&mut self) -> Declaration {
+        let declaration = match self.try_parse_declaration() {
+            Some(declaration) => declaration,
+            None => panic!("Invalid declaration before position {}", self.pos)
+        };
+
+        if !self.eof() {
+            assert_eq!(self.consume_char(), ';');
+        }
+
+        declaration
+    }
+
+    /// Parse `<property>: <value> [!important]`, without the semicolon.
+    /// Return `None` if the declaration is malformed or uses unsupported syntax.
+    /// 解析 `<property>: <value> [!important]`（不含分号），格式错误或不支持时返回 `None`
+    fn try_parse_declaration(&mut self) -> Option<Declaration> {
         let property_name = self.parse_identifier();
+        if property_name.is_empty() {
+            return None;
+        }
         self.consume_whitespace();
-        assert_eq!(self.consume_char(), ':');
+        self.expect_char(':')?;
         self.consume_whitespace();
-        let value = self.parse_value();
+        let value = self.parse_value()?;
         self.consume_whitespace();
-        assert_eq!(self.consume_char(), ';');
 
-        Declaration {
-            name: property_name,
-            value
+        let mut important = false;
+        if self.peek_char() == Some('!') {
+            self.consume_char();
+            self.consume_whitespace();
+            if !self.parse_identifier().eq_ignore_ascii_case("important") {
+                return None;
+            }
+            self.consume_whitespace();
+            important = true;
         }
+
+        Some(Declaration {
+            name: property_name,
+            value,
+            important
+        })
     }
 
-    /// 解析属性值
-    fn parse_value(&mut self) -> Value {
-        match self.next_char() {
-            '0'..='9' => self.parse_length(),
+    /// 解析属性值，不支持的值返回 `None`
+    fn parse_value(&mut self) -> Option<Value> {
+        match self.peek_char()? {
+            '0'..='9' | '.' => self.parse_length(),
             '#' => self.parse_color(),
-            _ => Value::Keyword(self.parse_identifier())
+            _ => {
+                let name = self.parse_identifier();
+                if name.is_empty() {
+                    None
+                } else if name == "linear-gradient" && self.peek_char() == Some('(') {
+                    self.parse_linear_gradient()
+                } else {
+                    Some(Value::Keyword(name))
+                }
+            }
+        }
+    }
+
+    /// Parse the arguments of `linear-gradient(<angle>, <color>, <color>)`.
+    /// The angle is optional and defaults to `to bottom`.
+    /// 解析 `linear-gradient(...)` 的参数，角度可省略，默认为 `to bottom`
+    fn parse_linear_gradient(&mut self) -> Option<Value> {
+        self.expect_char('(')?;
+        self.consume_whitespace();
+
+        let angle = match self.peek_char()? {
+            '#' => 180.0,
+            _ => {
+                let angle = self.parse_angle()?;
+                self.consume_whitespace();
+                self.expect_char(',')?;
+                angle
+            }
+        };
+
+        let mut stops = Vec::new();
+        loop {
+            self.consume_whitespace();
+            stops.push(self.parse_hex_color()?);
+            self.consume_whitespace();
+            match self.peek_char()? {
+                ',' => { self.consume_char(); }
+                ')' => { self.consume_char(); break; }
+                _ => return None
+            }
+        }
+
+        // linear-gradient 至少需要两个颜色
+        if stops.len() < 2 {
+            return None;
+        }
+        Some(Value::LinearGradient(angle, stops))
+    }
+
+    /// Parse a gradient angle, either `<number>deg` or `to top|right|bottom|left`.
+    /// 解析渐变角度，`<number>deg` 或 `to top|right|bottom|left`
+    fn parse_angle(&mut self) -> Option<f32> {
+        match self.peek_char()? {
+            '0'..='9' | '.' | '-' | '+' => {
+                // 角度可以带正负号
+                let sign = match self.next_char() {
+                    '-' => { self.consume_char(); -1.0 }
+                    '+' => { self.consume_char(); 1.0 }
+                    _ => 1.0
+                };
+                let angle = self.parse_float()?;
+                if !self.parse_identifier().eq_ignore_ascii_case("deg") {
+                    return None;
+                }
+                Some(sign * angle)
+            }
+            _ => {
+                if self.parse_identifier() != "to" {
+                    return None;
+                }
+                self.consume_whitespace();
+                match &*self.parse_identifier() {
+                    "top" => Some(0.0),
+                    "right" => Some(90.0),
+                    "bottom" => Some(180.0),
+                    "left" => Some(270.0),
+                    _ => None
+                }
+            }
         }
     }
 
-    fn parse_length(&mut self) -> Value {
-        Value::Length(self.parse_float(), self.parse_unit())
+    /// Parse a length, or a plain number if no unit follows.
+    /// 解析长度，如果后面没有单位，则解析为数字
+    fn parse_length(&mut self) -> Option<Value> {
+        let f = self.parse_float()?;
+        match self.peek_char() {
+            Some(c) if valid_identifier_char(c) => Some(Value::Length(f, self.parse_unit()?)),
+            _ => Some(Value::Number(f))
+        }
     }
 
-    fn parse_float(&mut self) -> f32 {
+    fn parse_float(&mut self) -> Option<f32> {
         let s = self.consume_while(|c| match c {
             '0'..='9' | '.' => true,
             _ => false
         });
 
-        s.parse().unwrap()
+        s.parse().ok()
     }
 
-    fn parse_unit(&mut self) -> Unit {
+    /// 解析单位，不认识的单位返回 `None`
+    fn parse_unit(&mut self) -> Option<Unit> {
         match &*self.parse_identifier().to_ascii_lowercase() {
-            "px" => Unit::Px,
-            _ => panic!("unrecognized unit")
+            "px" => Some(Unit::Px),
+            "vw" => Some(Unit::Vw),
+            "vh" => Some(Unit::Vh),
+            "vmin" => Some(Unit::Vmin),
+            "vmax" => Some(Unit::Vmax),
+            _ => None
         }
     }
 
-    fn parse_color(&mut self) -> Value {
-        assert_eq!(self.consume_char(), '#');
-        Value::ColorValue(Color {
-            r: self.parse_hex_pair(),
-            g: self.parse_hex_pair(),
-            b: self.parse_hex_pair(),
+    fn parse_color(&mut self) -> Option<Value> {
+        self.parse_hex_color().map(Value::ColorValue)
+    }
+
+    /// Parse a `#rrggbb` color.
+    /// 解析 `#rrggbb` 颜色
+    fn parse_hex_color(&mut self) -> Option<Color> {
+        self.expect_char('#')?;
+        Some(Color {
+            r: self.parse_hex_pair()?,
+            g: self.parse_hex_pair()?,
+            b: self.parse_hex_pair()?,
             a: 255
         })
     }
 
-    fn parse_hex_pair(&mut self) -> u8 {
-        let s = &self.input[self.pos .. self.pos + 2];
+    fn parse_hex_pair(&mut self) -> Option<u8> {
+        let s = self.input.get(self.pos .. self.pos + 2)?;
+        let value = u8::from_str_radix(s, 16).ok()?;
         self.pos += 2;
-        u8::from_str_radix(s, 16).unwrap()
+        Some(value)
     }
 
     /// Parse a property name or keyword
@@ -334,6 +577,21 @@ impl Parser {
         self.input[self.pos..].chars().next().unwrap()
     }
 
+    // 获取下一个字符，不消耗；到结尾时返回 `None`
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    // 如果下一个字符是 `c` 则消费它，否则返回 `None`
+    fn expect_char(&mut self, c: char) -> Option<()> {
+        if self.peek_char() == Some(c) {
+            self.consume_char();
+            Some(())
+        } else {
+            None
+        }
+    }
+
     // 消费一个字符
     fn consume_char(&mut self) -> char {
         let mut iter = self.input[self.pos..].char_indices();
@@ -367,3 +625,84 @@ fn valid_identifier_char(c: char) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The value of the first declaration in `source`.
+    fn first_value(source: &str) -> Value {
+        parse(source.to_string()).rules.swap_remove(0).declarations.swap_remove(0).value
+    }
+
+    #[test]
+    fn parse_linear_gradient() {
+        let red = Color { r: 255, g: 0, b: 0, a: 255 };
+        let blue = Color { r: 0, g: 0, b: 255, a: 255 };
+
+        assert_eq!(first_value("a { background: linear-gradient(to right, #ff0000, #0000ff); }"),
+                   Value::LinearGradient(90.0, vec![red, blue]));
+        assert_eq!(first_value("a { background: linear-gradient(45deg, #ff0000, #0000ff); }"),
+                   Value::LinearGradient(45.0, vec![red, blue]));
+        assert_eq!(first_value("a { background: linear-gradient(#ff0000, #0000ff); }"),
+                   Value::LinearGradient(180.0, vec![red, blue]));
+        assert_eq!(first_value("a { background: linear-gradient(-90deg, #ff0000, #0000ff); }"),
+                   Value::LinearGradient(-90.0, vec![red, blue]));
+        assert_eq!(first_value("a { background: linear-gradient(+.5deg, #ff0000, #0000ff); }"),
+                   Value::LinearGradient(0.5, vec![red, blue]));
+    }
+
+    #[test]
+    fn parse_important_flag() {
+        let stylesheet = parse("a { color: #000001 !important; margin: 1px; }".to_string());
+        let declarations = &stylesheet.rules[0].declarations;
+        assert!(declarations[0].important);
+        assert!(!declarations[1].important);
+    }
+
+    #[test]
+    fn parse_inline_style_declarations() {
+        // The last semicolon is optional in a `style` attribute.
+        let declarations = parse_inline_style("color: #000001 ! important; margin: 1px".to_string());
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(declarations[0].name, "color");
+        assert!(declarations[0].important);
+        assert_eq!(declarations[1].value, Value::Length(1.0, Unit::Px));
+        assert!(!declarations[1].important);
+    }
+
+    #[test]
+    fn parse_inline_style_drops_unsupported_declarations() {
+        let declarations = parse_inline_style(
+            "width: 50%; color: #fff; height: 1px !bogus; : 2px; margin: 1px".to_string()
+        );
+        assert_eq!(declarations.len(), 1);
+        assert_eq!(declarations[0].name, "margin");
+        assert_eq!(declarations[0].value, Value::Length(1.0, Unit::Px));
+
+        let declarations = parse_inline_style("background: linear-gradient(45deg, #000000); color: #00000".to_string());
+        assert!(declarations.is_empty());
+    }
+
+    #[test]
+    fn parse_unitless_numbers() {
+        assert_eq!(first_value("a { opacity: 0.5; }"), Value::Number(0.5));
+        assert_eq!(first_value("a { opacity: .25; }"), Value::Number(0.25));
+        assert_eq!(first_value("a { width: 0; }"), Value::Number(0.0));
+        assert_eq!(first_value("a { width: 12px; }"), Value::Length(12.0, Unit::Px));
+        assert_eq!(Value::Number(0.0).to_px(), 0.0);
+    }
+
+    #[test]
+    fn attribute_selector_specificity() {
+        let stylesheet = parse("input[type=\"text\"][disabled].a { color: #000001; }".to_string());
+        let selector = &stylesheet.rules[0].selectors[0];
+        assert_eq!(selector.specificity(), (0, 3, 1));
+
+        let Selector::Simple(ref simple) = *selector;
+        assert_eq!(simple.attributes[0].name, "type");
+        assert_eq!(simple.attributes[0].operator, Some(AttrOperator::Equals));
+        assert_eq!(simple.attributes[0].value, Some("text".to_string()));
+        assert_eq!(simple.attributes[1].operator, None);
+    }
+}
@@ -29,11 +29,12 @@ pub fn text(data: String) -> Node {
 }
 
 // 生成一个元素节点
+// HTML 标签名不区分大小写，统一保存为小写
 pub fn element(name: String, attrs: AttrMap, children: Vec<Node>) -> Node {
     Node {
         children,
         node_type: NodeType::Element(ElementData {
-            tag_name: name,
+            tag_name: name.to_ascii_lowercase(),
             attributes: attrs
         })
     }
@@ -131,32 +131,76 @@ impl Parser {
         let tag_name = self.parse_tag_name();
         // 在开标签中匹配属性
         let attrs = self.parse_attributes();
+        // 自闭合标签 `<br/>`
+        let self_closing = self.starts_with("/");
+        if self_closing {
+            self.consume_char();
+        }
         assert_eq!(self.consume_char(), '>');
 
+        // Void elements like `<input>` have no contents and no closing tag.
+        // 空元素（如 `<input>`）没有内容，也没有闭合标签
+        if self_closing || is_void_element(&tag_name) {
+            // A redundant `</input>` right after a void element is skipped.
+            // 空元素后面紧跟的多余闭合标签（如 `</input>`）直接跳过
+            if is_void_element(&tag_name) {
+                self.consume_closing_tag(&tag_name);
+            }
+            return dom::element(tag_name, attrs, Vec::new());
+        }
+
         // Contents.
         // 元素内容
         let children = self.parse_nodes();
 
         // Closing tag.
-        // 解析闭合标签
+        // 解析闭合标签，标签名不区分大小写
         assert_eq!(self.consume_char(), '<');
         assert_eq!(self.consume_char(), '/');
-        assert_eq!(self.parse_tag_name(), tag_name);
+        let closing_tag_name = self.parse_tag_name();
+        assert!(closing_tag_name.eq_ignore_ascii_case(&tag_name), "Mismatched closing tag {} for {}", closing_tag_name, tag_name);
         assert_eq!(self.consume_char(), '>');
 
         dom::element(tag_name, attrs, children)
     }
 
-    // Parse a single name="value" pair
-    // 解析单个属性对
+    // Consume `</tag_name>` if it comes next, comparing the name case-insensitively
+    // 如果接下来是 `</tag_name>`（不区分大小写），则消耗它
+    fn consume_closing_tag(&mut self, tag_name: &str) {
+        let closing_tag = format!("</{}>", tag_name);
+        let matches_next = match self.input.get(self.pos .. self.pos + closing_tag.len()) {
+            Some(s) => s.eq_ignore_ascii_case(&closing_tag),
+            None => false
+        };
+        if matches_next {
+            self.pos += closing_tag.len();
+        }
+    }
+
+    // Parse a single name="value" pair, a name=value pair, or a boolean attribute like `disabled`
+    // 解析单个属性对，支持无引号的属性值和没有值的布尔属性
     fn parse_attr(&mut self) -> (String, String) {
-        let name = self.parse_tag_name();
+        // 属性名不区分大小写，统一保存为小写；属性值保留原样
+        let name = self.parse_tag_name().to_ascii_lowercase();
+        // 没有 `=` 的布尔属性，值为空字符串
+        if self.eof() || self.next_char() != '=' {
+            return (name, String::new());
+        }
         // 属性名后面跟着 `=`
         assert_eq!(self.consume_char(), '=');
-        let value = self.parse_attr_value();
+        let value = match self.next_char() {
+            '"' | '\'' => self.parse_attr_value(),
+            _ => self.parse_unquoted_attr_value()
+        };
         return (name, value);
     }
 
+    // Parse an unquoted value.
+    // 解析没有引号的属性值，直到遇上空白字符或 `>`
+    fn parse_unquoted_attr_value(&mut self) -> String {
+        self.consume_while(|c| !c.is_whitespace() && c != '>')
+    }
+
     // Parse a quoted value.
     // 解析引号包含的属性值
     fn parse_attr_value(&mut self) -> String {
@@ -180,11 +224,12 @@ impl Parser {
         loop {
 
             self.consume_whitespace();
-            if self.next_char() == '>' {
+            if self.next_char() == '>' || self.starts_with("/>") {
                 break;
             }
 
             let (name, value) = self.parse_attr();
+            assert!(!name.is_empty(), "Unexpected character {} in tag", self.next_char());
             attributes.insert(name, value);
         }
 
@@ -209,3 +254,84 @@ impl Parser {
     }
 
 }
+
+// Elements that never have contents or a closing tag.
+// 空元素：没有内容，也没有闭合标签
+fn is_void_element(tag_name: &str) -> bool {
+    matches!(
+        &*tag_name.to_ascii_lowercase(),
+        "area" | "base" | "br" | "col" | "embed" | "hr" | "img" | "input" |
+        "link" | "meta" | "source" | "track" | "wbr"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::{ElementData, Node, NodeType};
+
+    fn element_data(node: &Node) -> &ElementData {
+        match node.node_type {
+            NodeType::Element(ref elem) => elem,
+            NodeType::Text(_) => panic!("expected an element")
+        }
+    }
+
+    #[test]
+    fn boolean_attributes() {
+        let root = parse("<input disabled required>".to_string());
+        let elem = element_data(&root);
+        assert_eq!(elem.tag_name, "input");
+        assert_eq!(elem.attributes.len(), 2);
+        assert_eq!(elem.attributes.get("disabled"), Some(&String::new()));
+        assert_eq!(elem.attributes.get("required"), Some(&String::new()));
+    }
+
+    #[test]
+    fn unquoted_attribute_value() {
+        let root = parse("<div class=box></div>".to_string());
+        assert_eq!(element_data(&root).attributes.get("class"), Some(&"box".to_string()));
+    }
+
+    #[test]
+    fn quoted_attribute_values() {
+        let root = parse("<div id='a' class=\"b c\"></div>".to_string());
+        let elem = element_data(&root);
+        assert_eq!(elem.attributes.get("id"), Some(&"a".to_string()));
+        assert_eq!(elem.attributes.get("class"), Some(&"b c".to_string()));
+    }
+
+    #[test]
+    fn void_and_self_closing_elements() {
+        let root = parse("<p><input type=text><br/><span></span></p>".to_string());
+        let names: Vec<&str> = root.children.iter().map(|child| &*element_data(child).tag_name).collect();
+        assert_eq!(names, ["input", "br", "span"]);
+    }
+
+    #[test]
+    fn void_elements_skip_redundant_closing_tags() {
+        let root = parse("<div><input type=\"text\"></input><BR></br><span></span></div>".to_string());
+        let names: Vec<&str> = root.children.iter().map(|child| &*element_data(child).tag_name).collect();
+        assert_eq!(names, ["input", "br", "span"]);
+        assert!(root.children.iter().all(|child| child.children.is_empty()));
+
+        let root = parse("<input></input>".to_string());
+        assert_eq!(element_data(&root).tag_name, "input");
+        let root = parse("<br></br>".to_string());
+        assert_eq!(element_data(&root).tag_name, "br");
+    }
+
+    #[test]
+    fn tag_and_attribute_names_are_lowercased() {
+        let root = parse("<DIV CLASS=\"Box\"><P>Hi</p></DIV>".to_string());
+        let elem = element_data(&root);
+        assert_eq!(elem.tag_name, "div");
+        // Attribute values and text keep their case.
+        assert_eq!(elem.attributes.get("class"), Some(&"Box".to_string()));
+        assert_eq!(element_data(&root.children[0]).tag_name, "p");
+        match root.children[0].children[0].node_type {
+            NodeType::Text(ref text) => assert_eq!(text, "Hi"),
+            NodeType::Element(_) => panic!("expected a text node")
+        }
+    }
+}
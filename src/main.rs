@@ -43,8 +43,15 @@ fn main() {
     let layout_tree = layout::layout_tree(&style_tree, initial_containing_block);
     // println!("{:#?}", layout_tree);
 
+    // `--dump-layout` 输出布局树的几何尺寸，而不是生成图片
+    if std::env::args().any(|arg| arg == "--dump-layout") {
+        print!("{}", layout_tree.debug_tree(0));
+        return;
+    }
+
     // 创建绘制画布 栅格化
-    let canvas = painting::paint(&layout_tree, initial_containing_block.content);
+    // scale 为 1 时不做超采样抗锯齿
+    let canvas = painting::paint(&layout_tree, initial_containing_block.content, 1);
     // println!("{:#?}", canvas);
 
     // 将画布保存为图片
@@ -30,7 +30,13 @@ use crate::layout::{LayoutBox, Rect, BlockNode, InlineNode, AnonymousBlock};
 
 #[derive(Debug)]
 pub enum DisplayCommand {
-    SolidColor(Color, Rect)
+    SolidColor(Color, Rect),
+    /// A solid-color rectangle clipped to a rounded rectangle: `(color, rect, clip, radius)`.
+    /// 裁剪到圆角矩形内的纯色矩形
+    RoundedColor(Color, Rect, Rect, f32),
+    /// A linear gradient filling a rectangle with corners rounded by `radius`: `(rect, angle, colors, radius)`.
+    /// 填充圆角矩形的线性渐变
+    Gradient(Rect, f32, Vec<Color>, f32)
     // insert more commands here
 }
 
@@ -47,11 +53,52 @@ pub fn build_display_list(layout_root: &LayoutBox) -> DisplayList {
 }
 
 fn render_layout_box(list: &mut DisplayList, layout_box: &LayoutBox) {
+    let start = list.len();
+
     render_background(list, layout_box);
     render_borders(list, layout_box);
     for child in &layout_box.children {
         render_layout_box(list, child);
     }
+
+    // `opacity` applies to the whole subtree, so fade every command generated for this box and its descendants.
+    // `opacity` 作用于整个子树，因此要淡化这个盒子及其子孙生成的所有命令
+    let opacity = get_opacity(layout_box);
+    if opacity < 1.0 {
+        for item in &mut list[start..] {
+            item.multiply_alpha(opacity);
+        }
+    }
+}
+
+impl DisplayCommand {
+    /// Scale the geometry of this command by `factor`, for rendering at a higher resolution.
+    /// 将命令的几何尺寸放大 `factor` 倍，用于高分辨率绘制
+    fn scale_by(&mut self, factor: f32) {
+        match *self {
+            DisplayCommand::SolidColor(_, ref mut rect) => *rect = rect.scaled_by(factor),
+            DisplayCommand::RoundedColor(_, ref mut rect, ref mut clip, ref mut radius) => {
+                *rect = rect.scaled_by(factor);
+                *clip = clip.scaled_by(factor);
+                *radius *= factor;
+            }
+            DisplayCommand::Gradient(ref mut rect, _, _, ref mut radius) => {
+                *rect = rect.scaled_by(factor);
+                *radius *= factor;
+            }
+        }
+    }
+
+    /// Multiply the alpha of every color in this command by `factor`.
+    /// 将命令中所有颜色的 alpha 乘以 `factor`
+    fn multiply_alpha(&mut self, factor: f32) {
+        let fade = |color: &mut Color| color.a = (color.a as f32 * factor).round() as u8;
+        match *self {
+            DisplayCommand::SolidColor(ref mut color, _) |
+            DisplayCommand::RoundedColor(ref mut color, _, _, _) => fade(color),
+            DisplayCommand::Gradient(_, _, ref mut stops, _) => stops.iter_mut().for_each(fade)
+        }
+    }
 }
 
 // By default, HTML elements are stacked in the order they appear: If two elements overlap,
@@ -70,10 +117,39 @@ fn render_layout_box(list: &mut DisplayList, layout_box: &LayoutBox) {
 
 fn render_background(list: &mut DisplayList, layout_box: &LayoutBox) {
     if let Some(color) = get_color(layout_box, "background") {
-        list.push(DisplayCommand::SolidColor(color, layout_box.dimensions.border_box()));
+        push_solid_color(list, layout_box, color, layout_box.dimensions.border_box());
+    } else if let Some((angle, stops)) = get_gradient(layout_box, "background") {
+        let border_box = layout_box.dimensions.border_box();
+        let radius = get_border_radius(layout_box, border_box);
+        list.push(DisplayCommand::Gradient(border_box, angle, stops, radius));
+    }
+}
+
+/// Push a solid-color rectangle, clipped to the rounded border box if `border-radius` is set.
+/// 添加纯色矩形，如果设置了 `border-radius`，则裁剪到圆角边框盒内
+fn push_solid_color(list: &mut DisplayList, layout_box: &LayoutBox, color: Color, rect: Rect) {
+    let border_box = layout_box.dimensions.border_box();
+    let radius = get_border_radius(layout_box, border_box);
+    if radius > 0.0 {
+        list.push(DisplayCommand::RoundedColor(color, rect, border_box, radius));
+    } else {
+        list.push(DisplayCommand::SolidColor(color, rect));
     }
 }
 
+/// Return the used `border-radius`, clamped to half the smaller side of `border_box`.
+/// 返回 `border-radius` 的使用值，最大为 `border_box` 较短边的一半
+/// Only `px` lengths are supported: viewport units are resolved during layout, which painting doesn't see.
+/// 只支持 `px` 长度：视口单位在布局阶段解析，绘制阶段无法得到
+fn get_border_radius(layout_box: &LayoutBox, border_box: Rect) -> f32 {
+    let radius = match layout_box.box_type {
+        BlockNode(style) | InlineNode(style) => style.value("border-radius").map_or(0.0, |v| v.to_px()),
+        AnonymousBlock => 0.0
+    };
+
+    radius.min(border_box.width.min(border_box.height) / 2.0).max(0.0)
+}
+
 /// Return the specified color for CSS property `name`, or None if no color was specified.
 /// 返回 CSS 属性 `name` 的指定颜色，如果没有指定颜色，则返回 None
 fn get_color(layout_box: &LayoutBox, name: &str) -> Option<Color> {
@@ -86,6 +162,30 @@ fn get_color(layout_box: &LayoutBox, name: &str) -> Option<Color> {
     }
 }
 
+/// Return the used `opacity`, clamped to [0, 1]. Defaults to 1.0 (opaque).
+/// 返回 `opacity` 的使用值，限制在 [0, 1] 之间，默认为 1.0（不透明）
+fn get_opacity(layout_box: &LayoutBox) -> f32 {
+    match layout_box.box_type {
+        BlockNode(style) | InlineNode(style) => match style.value("opacity") {
+            Some(Value::Number(opacity)) => opacity.clamp(0.0, 1.0),
+            _ => 1.0
+        },
+        AnonymousBlock => 1.0
+    }
+}
+
+/// Return the specified `linear-gradient` for CSS property `name`, or None if there is none.
+/// 返回 CSS 属性 `name` 指定的线性渐变，如果没有，则返回 None
+fn get_gradient(layout_box: &LayoutBox, name: &str) -> Option<(f32, Vec<Color>)> {
+    match layout_box.box_type {
+        BlockNode(style) | InlineNode(style) => match style.value(name) {
+            Some(Value::LinearGradient(angle, stops)) => Some((angle, stops)),
+            _ => None
+        },
+        AnonymousBlock => None
+    }
+}
+
 // The borders are similar, but instead of a single rectangle we draw four—one for each edge of the box.
 // 边框是相似的，但我们绘制的不是一个矩形，而是四个矩形——一个用于框的每个边缘
 
@@ -100,36 +200,36 @@ fn render_borders(list: &mut DisplayList, layout_box: &LayoutBox) {
     let border_box = d.border_box();
 
     // Left border
-    list.push(DisplayCommand::SolidColor(color, Rect {
+    push_solid_color(list, layout_box, color, Rect {
         x: border_box.x,
         y: border_box.y,
         width: d.border.left,
         height: border_box.height
-    }));
+    });
 
     // Right border
-    list.push(DisplayCommand::SolidColor(color, Rect {
+    push_solid_color(list, layout_box, color, Rect {
         x: border_box.x + border_box.width - d.border.right,
         y: border_box.y,
         width: d.border.right,
         height: border_box.height
-    }));
+    });
 
     // Top border
-    list.push(DisplayCommand::SolidColor(color, Rect {
+    push_solid_color(list, layout_box, color, Rect {
         x: border_box.x,
         y: border_box.y,
         width: border_box.width,
         height: d.border.top
-    }));
+    });
 
     // Bottom border
-    list.push(DisplayCommand::SolidColor(color, Rect {
+    push_solid_color(list, layout_box, color, Rect {
         x: border_box.x,
         y: border_box.y + border_box.height - d.border.bottom,
         width: border_box.width,
         height: d.border.bottom
-    }));
+    });
 
 }
 
@@ -164,40 +264,300 @@ impl Canvas {
     fn paint_item(&mut self, item: &DisplayCommand) {
         match *item {
             DisplayCommand::SolidColor(color, rect) => {
-                let x0 = rect.x.clamp(0.0, self.width as f32) as usize;
-                let y0 = rect.y.clamp(0.0, self.height as f32) as usize;
-                let x1 = (rect.x + rect.width).clamp(0.0, self.width as f32) as usize;
-                let y1 = (rect.y + rect.height).clamp(0.0, self.height as f32) as usize;
+                let (x0, y0, x1, y1) = self.pixel_bounds(rect);
+
+                for y in y0 .. y1 {
+                    for x in x0 .. x1 {
+                        self.blend_pixel(x, y, color);
+                    }
+                }
+            }
+            DisplayCommand::RoundedColor(color, rect, clip, radius) => {
+                let (x0, y0, x1, y1) = self.pixel_bounds(rect);
 
                 for y in y0 .. y1 {
                     for x in x0 .. x1 {
-                        // TODO: alpha compositing with existing pixel
-                        self.pixels[y * self.width + x] = color;
+                        // Test the pixel center against the rounded corners.
+                        // 用像素中心判断是否在圆角之内
+                        if inside_rounded_rect(clip, radius, x as f32 + 0.5, y as f32 + 0.5) {
+                            self.blend_pixel(x, y, color);
+                        }
+                    }
+                }
+            }
+            DisplayCommand::Gradient(rect, angle, ref stops, radius) => {
+                let (x0, y0, x1, y1) = self.pixel_bounds(rect);
+
+                // CSS angles start at "to top" and turn clockwise, with y pointing down.
+                // The gradient line is long enough for the corners to get the end colors.
+                // CSS 角度从 "to top" 开始顺时针旋转（y 轴向下）。渐变线的长度使得角落恰好取到两端的颜色
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let length = (rect.width * sin).abs() + (rect.height * cos).abs();
+                let center_x = rect.x + rect.width / 2.0;
+                let center_y = rect.y + rect.height / 2.0;
+
+                for y in y0 .. y1 {
+                    for x in x0 .. x1 {
+                        if radius > 0.0 && !inside_rounded_rect(rect, radius, x as f32 + 0.5, y as f32 + 0.5) {
+                            continue;
+                        }
+
+                        let dx = x as f32 + 0.5 - center_x;
+                        let dy = y as f32 + 0.5 - center_y;
+                        let t = if length > 0.0 { (dx * sin - dy * cos) / length + 0.5 } else { 0.0 };
+                        self.blend_pixel(x, y, gradient_color(stops, t));
                     }
                 }
             }
         }
+    }
 
-        // Note that this code only works with opaque colors.
-        // If we added transparency (by reading the opacity property,
-        // or adding support for rgba() values in the CSS parser) then it would
-        // need to blend each new pixel with whatever it's drawn on top of.
-        // 请注意，此代码仅适用于不透明的颜色。如果我们添加透明度（通过读取 opacity 属性，
-        // 或在 CSS 解析器中添加对 rgba() 值的支持），那么它需要将每个新像素与它上面绘制的任何内容混合
+    /// Composite `color` over the pixel at `(x, y)` using its alpha channel.
+    /// Colors made transparent by the opacity property are blended with whatever they are drawn on top of.
+    /// 使用 alpha 通道将 `color` 合成到 `(x, y)` 处的像素上。被 opacity 属性变透明的颜色会与它下面已绘制的内容混合
+    fn blend_pixel(&mut self, x: usize, y: usize, color: Color) {
+        let pixel = &mut self.pixels[y * self.width + x];
+        *pixel = blend(color, *pixel);
     }
+
+    /// Shrink a canvas rendered at `scale` times the target size, averaging each `scale`×`scale` block of pixels.
+    /// 缩小以 `scale` 倍尺寸绘制的画布，每个 `scale`×`scale` 的像素块取平均值
+    fn downsample(&self, scale: usize) -> Canvas {
+        let width = self.width / scale;
+        let height = self.height / scale;
+        let samples = (scale * scale) as u32;
+        let average = |sum: u32| ((sum + samples / 2) / samples) as u8;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0 .. height {
+            for x in 0 .. width {
+                let (mut r, mut g, mut b, mut a) = (0, 0, 0, 0);
+                for sy in y * scale .. (y + 1) * scale {
+                    for sx in x * scale .. (x + 1) * scale {
+                        let pixel = self.pixels[sy * self.width + sx];
+                        r += pixel.r as u32;
+                        g += pixel.g as u32;
+                        b += pixel.b as u32;
+                        a += pixel.a as u32;
+                    }
+                }
+                pixels.push(Color { r: average(r), g: average(g), b: average(b), a: average(a) });
+            }
+        }
+
+        Canvas { width, height, pixels }
+    }
+
+    /// The pixel range `(x0, y0, x1, y1)` covered by `rect`, clamped to the canvas.
+    /// `rect` 覆盖的像素范围，限制在画布之内
+    fn pixel_bounds(&self, rect: Rect) -> (usize, usize, usize, usize) {
+        let x0 = rect.x.clamp(0.0, self.width as f32) as usize;
+        let y0 = rect.y.clamp(0.0, self.height as f32) as usize;
+        let x1 = (rect.x + rect.width).clamp(0.0, self.width as f32) as usize;
+        let y1 = (rect.y + rect.height).clamp(0.0, self.height as f32) as usize;
+
+        (x0, y0, x1, y1)
+    }
+}
+
+/// Whether the point `(x, y)` lies inside `rect` with its corners rounded by `radius`.
+/// `radius` should not exceed half of the smaller side of `rect`.
+/// 点 `(x, y)` 是否位于圆角半径为 `radius` 的矩形 `rect` 内
+fn inside_rounded_rect(rect: Rect, radius: f32, x: f32, y: f32) -> bool {
+    // The nearest point of the rectangle shrunk by `radius` is the center of the closest corner arc.
+    // Rounding can make the shrunk rectangle slightly inverted, so keep each upper bound above its lower bound.
+    // 缩小 `radius` 后的矩形上最近的点，就是最近的圆角圆心。浮点舍入可能使缩小后的矩形略微反转，因此保证上界不小于下界
+    let (left, top) = (rect.x + radius, rect.y + radius);
+    let right = (rect.x + rect.width - radius).max(left);
+    let bottom = (rect.y + rect.height - radius).max(top);
+    let cx = x.clamp(left, right);
+    let cy = y.clamp(top, bottom);
+    let (dx, dy) = (x - cx, y - cy);
+
+    dx * dx + dy * dy <= radius * radius
+}
+
+/// Composite `src` over `dst` ("source over"). Opaque colors simply replace `dst`.
+/// 将 `src` 合成到 `dst` 之上，不透明的颜色直接替换 `dst`
+fn blend(src: Color, dst: Color) -> Color {
+    if src.a == 255 {
+        return src;
+    }
+
+    let alpha = src.a as f32 / 255.0;
+    let mix = |s: u8, d: u8| (s as f32 * alpha + d as f32 * (1.0 - alpha)).round() as u8;
+
+    Color {
+        r: mix(src.r, dst.r),
+        g: mix(src.g, dst.g),
+        b: mix(src.b, dst.b),
+        a: (src.a as f32 + dst.a as f32 * (1.0 - alpha)).round() as u8
+    }
+}
+
+/// The color at position `t` (0.0 to 1.0) of a gradient with evenly spaced `stops`.
+/// 均匀分布的渐变色 `stops` 在位置 `t`（0.0 到 1.0）处的颜色
+fn gradient_color(stops: &[Color], t: f32) -> Color {
+    let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let i = (position as usize).min(stops.len() - 2);
+    let f = position - i as f32;
+    let (a, b) = (stops[i], stops[i + 1]);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+
+    Color { r: mix(a.r, b.r), g: mix(a.g, b.g), b: mix(a.b, b.b), a: mix(a.a, b.a) }
 }
 
 // Now we can put everything together in the paint function, which builds a display list and then rasterizes it to a canvas:
 // 现在我们可以将所有内容放在paint函数中，它会构建一个显示列表，然后将其光栅化到画布上
 
 /// Paint a tree of LayoutBoxes to an array of pixels.
+/// With `scale` > 1 the canvas is rendered at `scale` times the size and then averaged back down,
+/// which anti-aliases the edges of boxes at fractional positions. A `scale` of 1 disables this.
 /// 将布局框树绘制到像素数组
-pub fn paint(layout_root: &LayoutBox, bounds: Rect) -> Canvas {
+/// `scale` 大于 1 时，先以 `scale` 倍的尺寸绘制画布再平均缩小，从而对小数位置的盒子边缘做抗锯齿。`scale` 为 1 时不做处理
+pub fn paint(layout_root: &LayoutBox, bounds: Rect, scale: usize) -> Canvas {
+    assert!(scale >= 1, "scale must be at least 1");
+
     let display_list = build_display_list(layout_root);
-    let mut canvas = Canvas::new(bounds.width as usize, bounds.height as usize);
-    for item in display_list {
+    let width = bounds.width as usize;
+    let height = bounds.height as usize;
+    let mut canvas = Canvas::new(width * scale, height * scale);
+    for mut item in display_list {
+        if scale > 1 {
+            item.scale_by(scale as f32);
+        }
         canvas.paint_item(&item);
     }
 
-    canvas
-}
\ No newline at end of file
+    if scale > 1 {
+        canvas.downsample(scale)
+    } else {
+        canvas
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{css, html, layout, style};
+
+    const WHITE: Color = Color { r: 255, g: 255, b: 255, a: 255 };
+
+    /// Parse `html` and `css`, lay them out in a viewport of the given size, and paint them at `scale`.
+    fn paint_source(html: &str, css: &str, width: f32, height: f32, scale: usize) -> Canvas {
+        let root = html::parse(html.to_string());
+        let stylesheet = css::parse(css.to_string());
+        let style_root = style::style_tree(&root, &stylesheet);
+        let viewport = layout::Dimensions {
+            content: Rect { x: 0.0, y: 0.0, width, height },
+            ..Default::default()
+        };
+        let layout_root = layout::layout_tree(&style_root, viewport);
+
+        paint(&layout_root, viewport.content, scale)
+    }
+
+    fn pixel(canvas: &Canvas, x: usize, y: usize) -> Color {
+        canvas.pixels[y * canvas.width + x]
+    }
+
+    #[test]
+    fn large_border_radius_leaves_corners_unpainted() {
+        let black = Color { r: 0, g: 0, b: 0, a: 255 };
+        let css = "div { display: block; height: 20px; background: #000000; border-radius: 500px; }";
+        let canvas = paint_source("<div></div>", css, 20.0, 20.0, 1);
+
+        assert_eq!(pixel(&canvas, 0, 0), WHITE);
+        assert_eq!(pixel(&canvas, 19, 0), WHITE);
+        assert_eq!(pixel(&canvas, 0, 19), WHITE);
+        assert_eq!(pixel(&canvas, 19, 19), WHITE);
+        assert_eq!(pixel(&canvas, 10, 10), black);
+    }
+
+    #[test]
+    fn zero_border_radius_paints_square_corners() {
+        let black = Color { r: 0, g: 0, b: 0, a: 255 };
+        let css = "div { display: block; height: 20px; background: #000000; }";
+        let canvas = paint_source("<div></div>", css, 20.0, 20.0, 1);
+
+        assert!(canvas.pixels.iter().all(|&p| p == black));
+    }
+
+    #[test]
+    fn border_radius_with_fractional_size() {
+        // The clamped radius used to leave the corner centers inverted by a rounding error.
+        let css = "div { display: block; margin-left: 0.1px; width: 3.3px; height: 100px; \
+                   background: #000000; border-radius: 500px; }";
+        paint_source("<div></div>", css, 10.0, 100.0, 1);
+        paint_source("<div></div>", css, 10.0, 100.0, 2);
+
+        let rect = Rect { x: 0.1, y: 0.0, width: 3.3, height: 100.0 };
+        assert!(inside_rounded_rect(rect, rect.width / 2.0, 1.75, 50.0));
+    }
+
+    #[test]
+    fn horizontal_gradient_edges() {
+        let red = Color { r: 255, g: 0, b: 0, a: 255 };
+        let blue = Color { r: 0, g: 0, b: 255, a: 255 };
+        // Colors are sampled at pixel centers, so the box must be wider than 255px
+        // for the half-pixel offset at each edge to round away.
+        for angle in &["to right", "90deg"] {
+            let css = format!("div {{ display: block; height: 10px; background: linear-gradient({}, #ff0000, #0000ff); }}", angle);
+            let canvas = paint_source("<div></div>", &css, 400.0, 10.0, 1);
+
+            assert_eq!(pixel(&canvas, 0, 5), red);
+            assert_eq!(pixel(&canvas, 399, 5), blue);
+        }
+    }
+
+    #[test]
+    fn gradient_respects_border_radius() {
+        let css = "div { display: block; height: 20px; border-radius: 10px; \
+                   background: linear-gradient(to bottom, #ff0000, #0000ff); }";
+        let canvas = paint_source("<div></div>", css, 20.0, 20.0, 1);
+
+        assert_eq!(pixel(&canvas, 0, 0), WHITE);
+        assert_eq!(pixel(&canvas, 19, 19), WHITE);
+        assert_ne!(pixel(&canvas, 10, 10), WHITE);
+    }
+
+    #[test]
+    fn opacity_blends_subtree_with_background() {
+        let html = "<div><p></p></div>";
+        let css = "div, p { display: block; } div { opacity: 0.5; } p { height: 10px; background: #000000; }";
+        let canvas = paint_source(html, css, 10.0, 10.0, 1);
+
+        // Half of the black child shows through over the white canvas.
+        let gray = pixel(&canvas, 5, 5);
+        assert_eq!((gray.r, gray.g, gray.b, gray.a), (127, 127, 127, 255));
+    }
+
+    #[test]
+    fn opacity_defaults_to_opaque_and_is_clamped() {
+        let black = Color { r: 0, g: 0, b: 0, a: 255 };
+        for opacity in &["", "opacity: 1;", "opacity: 2;"] {
+            let css = format!("div {{ display: block; height: 10px; background: #000000; {} }}", opacity);
+            let canvas = paint_source("<div></div>", &css, 10.0, 10.0, 1);
+            assert_eq!(pixel(&canvas, 5, 5), black);
+        }
+
+        let css = "div { display: block; height: 10px; background: #000000; opacity: 0; }";
+        let canvas = paint_source("<div></div>", css, 10.0, 10.0, 1);
+        assert_eq!(pixel(&canvas, 5, 5), WHITE);
+    }
+
+    #[test]
+    fn supersampling_blends_fractional_edges() {
+        let html = "<div><p></p></div>";
+        let css = "div, p { display: block; } p { margin-left: 2.5px; width: 4.5px; height: 2px; background: #000000; }";
+        let row = |canvas: &Canvas| canvas.pixels[..10].iter().map(|p| p.r).collect::<Vec<_>>();
+
+        // Without supersampling, edges snap to whole pixels.
+        let canvas = paint_source(html, css, 10.0, 2.0, 1);
+        assert_eq!(row(&canvas), [255, 255, 0, 0, 0, 0, 0, 255, 255, 255]);
+
+        // At scale 2, the pixel half covered by the box gets an intermediate value.
+        let canvas = paint_source(html, css, 10.0, 2.0, 2);
+        assert_eq!((canvas.width, canvas.height), (10, 2));
+        assert_eq!(row(&canvas), [255, 255, 128, 0, 0, 0, 0, 255, 255, 255]);
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use crate::dom::{ElementData, Node};
-use crate::css::{SimpleSelector, Specificity, Stylesheet, Selector, Rule, Value};
+use crate::css::{self, SimpleSelector, AttributeSelector, AttrOperator, Specificity, Stylesheet, Selector, Rule, Value};
 use crate::dom::NodeType;
 
 /// This article will cover what the CSS standard calls assigning property values,
@@ -35,6 +35,13 @@ pub enum Display {
     Node
 }
 
+#[derive(PartialEq, Clone, Copy)]
+pub enum Position {
+    Static,
+    Relative,
+    Fixed
+}
+
 
 /**
     What's with all the 'a stuff? Those are lifetimes,
@@ -95,6 +102,12 @@ fn matches_simple_selector(elem: &ElementData, selector: &SimpleSelector) -> boo
         return false;
     }
 
+    // Check attribute selectors
+    // 属性不匹配
+    if selector.attributes.iter().any(|attr| !matches_attribute_selector(elem, attr)) {
+        return false;
+    }
+
     // We didn't find any non-matching selector components.
     // 都匹配
     return true;
@@ -106,6 +119,27 @@ fn matches_simple_selector(elem: &ElementData, selector: &SimpleSelector) -> boo
      */
 }
 
+/// An attribute selector matches if the element has the attribute,
+/// and its value satisfies the operator (if any).
+/// 如果元素拥有该属性，并且属性值满足运算符（如果有），则属性选择器匹配
+fn matches_attribute_selector(elem: &ElementData, selector: &AttributeSelector) -> bool {
+    let actual = match elem.attributes.get(&selector.name) {
+        Some(value) => value,
+        None => return false
+    };
+
+    match (selector.operator, &selector.value) {
+        (Some(operator), Some(expected)) => match operator {
+            AttrOperator::Equals => actual == expected,
+            AttrOperator::Includes => actual.split_whitespace().any(|word| word == expected),
+            AttrOperator::Prefix => !expected.is_empty() && actual.starts_with(&**expected),
+            AttrOperator::Suffix => !expected.is_empty() && actual.ends_with(&**expected),
+            AttrOperator::Substring => !expected.is_empty() && actual.contains(&**expected)
+        },
+        _ => true
+    }
+}
+
 /// Next we need to traverse the DOM tree. For each element in the tree,
 /// we will search the stylesheet for matching rules.
 /// 接下来我们需要遍历 DOM 树。对于树中的每个元素，我们将在样式表中搜索匹配规则。
@@ -141,16 +175,28 @@ fn matching_rules<'a>(elem: &ElementData, stylesheet: &'a Stylesheet) -> Vec<Mat
 /// 我们按优先级对匹配进行排序，因此更高优先级的规则在低优先级的规则之后处理，并且可以覆盖它们在 HashMap 中的值。
 
 /// Apply styles to a single element, returning the specified values.
+/// `!important` declarations are applied in a second pass, so they override every normal one.
+/// Within each pass, the inline `style` attribute comes after the stylesheet.
 /// 将样式应用于单个元素，返回指定的值
+/// `!important` 声明在第二轮应用，因此会覆盖所有普通声明。每一轮中，行内 `style` 属性都在样式表之后应用。
 fn specified_values(elem: &ElementData, stylesheet: &Stylesheet) -> PropertyMap {
     let mut values: PropertyMap = HashMap::new();
     let mut rules = matching_rules(elem, stylesheet);
+    let inline = match elem.attributes.get("style") {
+        Some(style) => css::parse_inline_style(style.clone()),
+        None => Vec::new()
+    };
 
     // Go through the rules from lowest to highest specificity.
     // 通过从最低到最高优先级的规则
     rules.sort_by(|&(a, _), &(b, _)| a.cmp(&b));
-    for (_, rule) in rules {
-        for declaration in &rule.declarations {
+    for &important in &[false, true] {
+        let declarations = rules.iter()
+            .flat_map(|&(_, rule)| rule.declarations.iter())
+            .chain(inline.iter())
+            .filter(|declaration| declaration.important == important);
+
+        for declaration in declarations {
             values.insert(declaration.name.clone(), declaration.value.clone());
         }
     }
@@ -199,6 +245,19 @@ impl<'a> StyledNode<'a> {
         }
     }
 
+    /// The value of the `position` property (defaults to static).
+    ///  `position` 属性的值（默认为 static）。
+    pub fn position(&self) -> Position {
+        match self.value("position") {
+            Some(Value::Keyword(s)) => match &*s {
+                "relative" => Position::Relative,
+                "fixed" => Position::Fixed,
+                _ => Position::Static
+            },
+            _ => Position::Static
+        }
+    }
+
     /// Return the specified value of property `name`, or property `fallback_name` if that doesn't
     /// exist, or value `default` if neither does.
     /// 返回属性 `name` 的指定值，如果没有，则返回属性 `fallback_name`
@@ -209,3 +268,119 @@ impl<'a> StyledNode<'a> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::Color;
+    use crate::html;
+
+    /// The specified values of the root element of `html` under the stylesheet `css`.
+    fn root_values(html: &str, css: &str) -> PropertyMap {
+        let root = html::parse(html.to_string());
+        let stylesheet = css::parse(css.to_string());
+        match root.node_type {
+            NodeType::Element(ref elem) => specified_values(elem, &stylesheet),
+            NodeType::Text(_) => panic!("root is not an element")
+        }
+    }
+
+    fn color(b: u8) -> Value {
+        Value::ColorValue(Color { r: 0, g: 0, b, a: 255 })
+    }
+
+    #[test]
+    fn important_beats_higher_specificity() {
+        let values = root_values(
+            "<div id=\"x\" class=\"a\"></div>",
+            "div#x.a { color: #000001; } div { color: #000002 !important; }"
+        );
+        assert_eq!(values.get("color"), Some(&color(2)));
+    }
+
+    #[test]
+    fn inline_style_beats_stylesheet() {
+        let values = root_values(
+            "<div id=\"x\" style=\"color: #000003; margin: 1px\"></div>",
+            "div#x { color: #000001; margin: 2px; }"
+        );
+        assert_eq!(values.get("color"), Some(&color(3)));
+        assert_eq!(values.get("margin"), Some(&Value::Length(1.0, css::Unit::Px)));
+    }
+
+    #[test]
+    fn unsupported_inline_values_are_ignored() {
+        let values = root_values(
+            "<div id=\"x\" style=\"width: 50%; color: #fff; margin: 1px\"></div>",
+            "div#x { width: 2px; color: #000001; }"
+        );
+        assert_eq!(values.get("width"), Some(&Value::Length(2.0, css::Unit::Px)));
+        assert_eq!(values.get("color"), Some(&color(1)));
+        assert_eq!(values.get("margin"), Some(&Value::Length(1.0, css::Unit::Px)));
+    }
+
+    #[test]
+    fn inline_important_beats_stylesheet_important() {
+        let values = root_values(
+            "<div id=\"x\" style=\"color: #000003 !important; margin: 1px\"></div>",
+            "div#x { color: #000001 !important; margin: 2px !important; }"
+        );
+        assert_eq!(values.get("color"), Some(&color(3)));
+        assert_eq!(values.get("margin"), Some(&Value::Length(2.0, css::Unit::Px)));
+    }
+
+    #[test]
+    fn mixed_case_element_matches_lowercase_selector() {
+        let values = root_values("<DIV CLASS=\"Box\"></DIV>", "div.Box { color: #000001; } div.box { color: #000002; }");
+        assert_eq!(values.get("color"), Some(&color(1)));
+
+        let values = root_values("<div></div>", "DIV { color: #000001; }");
+        assert_eq!(values.get("color"), Some(&color(1)));
+    }
+
+    #[test]
+    fn attribute_presence_selector() {
+        let values = root_values("<input disabled>", "[disabled] { color: #000001; }");
+        assert_eq!(values.get("color"), Some(&color(1)));
+
+        let values = root_values("<input>", "[disabled] { color: #000001; }");
+        assert_eq!(values.get("color"), None);
+    }
+
+    #[test]
+    fn attribute_value_selectors() {
+        let css = "input[type=\"text\"] { color: #000001; } input[type='submit'] { color: #000002; }";
+        assert_eq!(root_values("<input type=text>", css).get("color"), Some(&color(1)));
+        assert_eq!(root_values("<input type=\"submit\">", css).get("color"), Some(&color(2)));
+        assert_eq!(root_values("<input type=\"checkbox\">", css).get("color"), None);
+    }
+
+    #[test]
+    fn attribute_operator_selectors() {
+        let html = "<a class=\"x y\" href=\"https://example.com/a.png\"></a>";
+        for selector in &["[class~=y]", "[href^=https]", "[href$=\".png\"]", "[href*=example]"] {
+            let values = root_values(html, &format!("{} {{ color: #000001; }}", selector));
+            assert_eq!(values.get("color"), Some(&color(1)), "{}", selector);
+        }
+        for selector in &["[class~=x-y]", "[href^=\"http:\"]", "[href$=jpg]", "[href*=\"\"]"] {
+            let values = root_values(html, &format!("{} {{ color: #000001; }}", selector));
+            assert_eq!(values.get("color"), None, "{}", selector);
+        }
+    }
+
+    #[test]
+    fn attribute_selector_counts_like_a_class() {
+        // `[type=text]` beats the tag-only selector that comes after it, but ties with the class.
+        let values = root_values(
+            "<input type=text class=\"a\">",
+            "[type=text] { color: #000001; } input { color: #000002; }"
+        );
+        assert_eq!(values.get("color"), Some(&color(1)));
+
+        let values = root_values(
+            "<input type=text class=\"a\">",
+            "[type=text] { color: #000001; } .a { color: #000002; }"
+        );
+        assert_eq!(values.get("color"), Some(&color(2)));
+    }
+}